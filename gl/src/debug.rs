@@ -15,24 +15,25 @@
 //!
 //! The debug font atlas was generated using: https://evanw.github.io/font-texture-generator/
 
-use crate::device::{Buffer, BufferTarget, BufferUploadMode, Program, Texture, Uniform, VertexAttr};
+use crate::device::{BlendState, BufferTarget, BufferUploadMode, Device, Primitive};
+use crate::device::{RenderState, UniformData, VertexAttrDescriptor, VertexAttrType};
 use euclid::Size2D;
-use gl::types::{GLfloat, GLint, GLsizei, GLuint};
-use gl;
 use pathfinder_geometry::basic::point::Point2DI32;
 use pathfinder_geometry::basic::rect::RectI32;
 use pathfinder_renderer::paint::ColorU;
 use serde_json;
+use std::cell::Cell;
 use std::collections::{HashMap, VecDeque};
+use std::collections::vec_deque;
 use std::fs::File;
 use std::io::BufReader;
-use std::ptr;
+use std::mem;
 use std::time::Duration;
 
 const SAMPLE_BUFFER_SIZE: usize = 60;
 
-const DEBUG_TEXTURE_VERTEX_SIZE: GLint = 8;
-const DEBUG_SOLID_VERTEX_SIZE:   GLint = 4;
+const DEBUG_TEXTURE_VERTEX_SIZE: i32 = 8;
+const DEBUG_SOLID_VERTEX_SIZE:   i32 = 4;
 
 pub const PADDING: i32 = 12;
 pub const BUTTON_WIDTH: i32 = PADDING * 2 + ICON_SIZE;
@@ -43,19 +44,35 @@ pub static TEXT_COLOR:   ColorU = ColorU { r: 255, g: 255, b: 255, a: 255      }
 pub static WINDOW_COLOR: ColorU = ColorU { r: 30,  g: 30,  b: 30,  a: 255 - 30 };
 
 const PERF_WINDOW_WIDTH: i32 = 300;
-const PERF_WINDOW_HEIGHT: i32 = LINE_HEIGHT * 2 + PADDING + 2;
+const PERF_WINDOW_HEIGHT: i32 =
+    (PADDING + LINE_HEIGHT + PADDING + PERF_GRAPH_HEIGHT) * 2 + PADDING;
 const FONT_ASCENT: i32 = 28;
 const LINE_HEIGHT: i32 = 42;
 const ICON_SIZE: i32 = 48;
 
+const PERF_GRAPH_HEIGHT: i32 = 32;
+const PERF_GRAPH_SCALE_MS: f64 = FRAME_BUDGET_MS * 2.0;
+const FRAME_BUDGET_MS: f64 = 16.6;
+
 static INVERTED_TEXT_COLOR: ColorU = ColorU { r: 0,   g: 0,   b: 0,   a: 255      };
 
+static GRAPH_COLOR: ColorU = ColorU { r: 0,   g: 200, b: 0,   a: 255 };
+static GRAPH_OVER_BUDGET_COLOR: ColorU = ColorU { r: 200, g: 0,   b: 0,   a: 255 };
+
 static JSON_PATH: &'static str = "resources/debug-font.json";
 
 static FONT_PNG_NAME: &'static str = "debug-font";
 
 static QUAD_INDICES: [u32; 6] = [0, 1, 3, 1, 2, 3];
 
+/// Horizontal alignment of a line of text within its layout rect.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 pub struct DebugFont {
@@ -83,39 +100,101 @@ struct DebugCharacter {
 
 impl DebugFont {
     fn load() -> DebugFont {
-        serde_json::from_reader(BufReader::new(File::open(JSON_PATH).unwrap())).unwrap()
+        DebugFont::load_from(JSON_PATH)
+    }
+
+    fn load_from(path: &str) -> DebugFont {
+        serde_json::from_reader(BufReader::new(File::open(path).unwrap())).unwrap()
+    }
+
+    // Drops any glyph whose atlas rect falls outside `texture_size`, warning once per
+    // offending character. A mismatched JSON/PNG pair would otherwise produce garbage UVs,
+    // so this runs once at load time rather than on every `glyph()` lookup.
+    fn retain_glyphs_within(&mut self, texture_size: Size2D<u32>) {
+        let font_name = self.name.clone();
+        self.characters.retain(|&character, info| {
+            let fits = info.x + info.width <= texture_size.width as i32 &&
+                info.y + info.height <= texture_size.height as i32;
+            if !fits {
+                eprintln!("debug font '{}': glyph '{}' lies outside the {}x{} atlas texture; \
+                           it will fall back to '?'",
+                          font_name,
+                          character,
+                          texture_size.width,
+                          texture_size.height);
+            }
+            fits
+        });
     }
 }
 
-pub struct DebugUI {
+pub struct DebugUI<D> where D: Device {
+    device: D,
     framebuffer_size: Size2D<u32>,
 
-    texture_program: DebugTextureProgram,
-    texture_vertex_array: DebugTextureVertexArray,
+    texture_program: DebugTextureProgram<D>,
+    texture_vertex_array: DebugTextureVertexArray<D>,
     font: DebugFont,
-    solid_program: DebugSolidProgram,
-    solid_vertex_array: DebugSolidVertexArray,
-    font_texture: Texture,
+    solid_program: DebugSolidProgram<D>,
+    solid_vertex_array: DebugSolidVertexArray<D>,
+    font_texture: D::Texture,
 
     cpu_samples: SampleBuffer,
     gpu_samples: SampleBuffer,
+
+    mouse_position: Point2DI32,
+    mouse_down: bool,
+    mouse_clicked: Cell<bool>,
 }
 
-impl DebugUI {
-    pub fn new(framebuffer_size: &Size2D<u32>) -> DebugUI {
-        let texture_program = DebugTextureProgram::new();
-        let texture_vertex_array = DebugTextureVertexArray::new(&texture_program);
-        let font = DebugFont::load();
+impl<D> DebugUI<D> where D: Device {
+    pub fn new(device: D, framebuffer_size: &Size2D<u32>) -> DebugUI<D> {
+        let atlas_png_path = format!("resources/textures/{}.png", FONT_PNG_NAME);
+        DebugUI::new_with_font(device, framebuffer_size, DebugFont::load(), &atlas_png_path)
+    }
 
-        let solid_program = DebugSolidProgram::new();
-        let solid_vertex_array = DebugSolidVertexArray::new(&solid_program);
-        solid_vertex_array.index_buffer.upload(&QUAD_INDICES,
-                                               BufferTarget::Index,
-                                               BufferUploadMode::Static);
+    /// Like `new()`, but loads the font atlas from `font_json_path`/`atlas_png_path` instead
+    /// of the crate's bundled one.
+    pub fn with_font(device: D,
+                     framebuffer_size: &Size2D<u32>,
+                     font_json_path: &str,
+                     atlas_png_path: &str)
+                     -> DebugUI<D> {
+        let font = DebugFont::load_from(font_json_path);
+        DebugUI::new_with_font(device, framebuffer_size, font, atlas_png_path)
+    }
 
-        let font_texture = Texture::from_png(FONT_PNG_NAME);
+    fn new_with_font(device: D,
+                     framebuffer_size: &Size2D<u32>,
+                     mut font: DebugFont,
+                     atlas_png_path: &str)
+                     -> DebugUI<D> {
+        let texture_program = DebugTextureProgram::new(&device);
+        let texture_vertex_array = DebugTextureVertexArray::new(&device, &texture_program);
+
+        let solid_program = DebugSolidProgram::new(&device);
+        let solid_vertex_array = DebugSolidVertexArray::new(&device, &solid_program);
+        device.allocate_buffer(&solid_vertex_array.index_buffer,
+                               &QUAD_INDICES,
+                               BufferTarget::Index,
+                               BufferUploadMode::Static);
+
+        let font_texture = device.create_texture_from_png(atlas_png_path);
+        let texture_size = device.texture_size(&font_texture);
+        if texture_size.width != font.width || texture_size.height != font.height {
+            eprintln!("debug font atlas '{}' is {}x{}, but {} declares {}x{}; glyph UVs may be \
+                       wrong",
+                      atlas_png_path,
+                      texture_size.width,
+                      texture_size.height,
+                      font.name,
+                      font.width,
+                      font.height);
+        }
+        font.retain_glyphs_within(texture_size);
 
         DebugUI {
+            device,
             framebuffer_size: *framebuffer_size,
             texture_program,
             texture_vertex_array,
@@ -125,6 +204,10 @@ impl DebugUI {
             font_texture,
             cpu_samples: SampleBuffer::new(),
             gpu_samples: SampleBuffer::new(),
+
+            mouse_position: Point2DI32::default(),
+            mouse_down: false,
+            mouse_clicked: Cell::new(false),
         }
     }
 
@@ -143,6 +226,19 @@ impl DebugUI {
         }
     }
 
+    pub fn set_mouse_position(&mut self, position: Point2DI32) {
+        self.mouse_position = position;
+    }
+
+    // Latches a click on the down transition; `draw()` clears the latch once the frame's
+    // buttons have had a chance to see it, so a held button doesn't re-click every frame.
+    pub fn set_mouse_down(&mut self, down: bool) {
+        if down && !self.mouse_down {
+            self.mouse_clicked.set(true);
+        }
+        self.mouse_down = down;
+    }
+
     pub fn draw(&self) {
         // Draw performance window.
         let bottom = self.framebuffer_size.height as i32 - PADDING;
@@ -151,15 +247,60 @@ impl DebugUI {
                             bottom - PERF_WINDOW_HEIGHT),
             Point2DI32::new(PERF_WINDOW_WIDTH, PERF_WINDOW_HEIGHT));
         self.draw_solid_rect(window_rect, WINDOW_COLOR);
-        self.draw_text(&format!("CPU: {:.3} ms", self.cpu_samples.mean_ms()),
-                       Point2DI32::new(window_rect.min_x() + PADDING,
-                                       window_rect.min_y() + PADDING + FONT_ASCENT),
-                       false);
-        self.draw_text(&format!("GPU: {:.3} ms", self.gpu_samples.mean_ms()),
-                        Point2DI32::new(
-                            window_rect.min_x() + PADDING,
-                            window_rect.min_y() + PADDING + FONT_ASCENT + LINE_HEIGHT),
-                       false);
+
+        let cpu_row_rect = RectI32::new(
+            Point2DI32::new(window_rect.min_x() + PADDING, window_rect.min_y() + PADDING),
+            Point2DI32::new(PERF_WINDOW_WIDTH - PADDING * 2, LINE_HEIGHT));
+        self.draw_text_wrapped("CPU", cpu_row_rect, TextAlign::Left, false);
+        self.draw_text_wrapped(&format!("{:.3} ms", self.cpu_samples.mean_ms()),
+                               cpu_row_rect,
+                               TextAlign::Right,
+                               false);
+        let cpu_graph_rect = RectI32::new(
+            Point2DI32::new(window_rect.min_x() + PADDING, cpu_row_rect.max_y() + PADDING),
+            Point2DI32::new(PERF_WINDOW_WIDTH - PADDING * 2, PERF_GRAPH_HEIGHT));
+        self.draw_graph(cpu_graph_rect, &self.cpu_samples, PERF_GRAPH_SCALE_MS);
+
+        let gpu_row_rect = RectI32::new(
+            Point2DI32::new(window_rect.min_x() + PADDING, cpu_graph_rect.max_y() + PADDING),
+            Point2DI32::new(PERF_WINDOW_WIDTH - PADDING * 2, LINE_HEIGHT));
+        self.draw_text_wrapped("GPU", gpu_row_rect, TextAlign::Left, false);
+        self.draw_text_wrapped(&format!("{:.3} ms", self.gpu_samples.mean_ms()),
+                               gpu_row_rect,
+                               TextAlign::Right,
+                               false);
+        let gpu_graph_rect = RectI32::new(
+            Point2DI32::new(window_rect.min_x() + PADDING, gpu_row_rect.max_y() + PADDING),
+            Point2DI32::new(PERF_WINDOW_WIDTH - PADDING * 2, PERF_GRAPH_HEIGHT));
+        self.draw_graph(gpu_graph_rect, &self.gpu_samples, PERF_GRAPH_SCALE_MS);
+
+        // This frame's buttons have all had a chance to see the click latch by now.
+        self.mouse_clicked.set(false);
+    }
+
+    /// Draws a bar graph of `samples` inside `rect`, one bar per sample, with a reference
+    /// line at the 60 fps (16.6 ms) frame budget.
+    pub fn draw_graph(&self, rect: RectI32, samples: &SampleBuffer, scale_ms: f64) {
+        self.draw_solid_rect(rect, WINDOW_COLOR);
+
+        let bar_width = rect.width() / SAMPLE_BUFFER_SIZE as i32;
+        let mut x = rect.min_x();
+        for sample in samples.iter() {
+            let sample_ms = duration_to_ms(*sample);
+            let height = (sample_ms / scale_ms * rect.height() as f64)
+                .min(rect.height() as f64)
+                .max(0.0) as i32;
+            let color = if sample_ms > FRAME_BUDGET_MS { GRAPH_OVER_BUDGET_COLOR } else { GRAPH_COLOR };
+            let bar_rect = RectI32::new(Point2DI32::new(x, rect.max_y() - height),
+                                        Point2DI32::new(bar_width, height));
+            self.draw_solid_rect(bar_rect, color);
+            x += bar_width;
+        }
+
+        let budget_y = rect.max_y() - (FRAME_BUDGET_MS / scale_ms * rect.height() as f64) as i32;
+        let budget_rect = RectI32::new(Point2DI32::new(rect.min_x(), budget_y),
+                                       Point2DI32::new(rect.width(), 0));
+        self.draw_rect_outline(budget_rect, TEXT_COLOR);
     }
 
     pub fn draw_solid_rect(&self, rect: RectI32, color: ColorU) {
@@ -177,26 +318,29 @@ impl DebugUI {
             DebugSolidVertex::new(rect.lower_right()),
             DebugSolidVertex::new(rect.lower_left()),
         ];
-        self.solid_vertex_array
-            .vertex_buffer
-            .upload(&vertex_data, BufferTarget::Vertex, BufferUploadMode::Dynamic);
-
-        unsafe {
-            gl::BindVertexArray(self.solid_vertex_array.gl_vertex_array);
-            gl::UseProgram(self.solid_program.program.gl_program);
-            gl::Uniform2f(self.solid_program.framebuffer_size_uniform.location,
-                          self.framebuffer_size.width as GLfloat,
-                          self.framebuffer_size.height as GLfloat);
-            set_color_uniform(&self.solid_program.color_uniform, color);
-            gl::BlendEquation(gl::FUNC_ADD);
-            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
-            gl::Enable(gl::BLEND);
-            if filled {
-                gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
-            } else {
-                gl::DrawArrays(gl::LINE_LOOP, 0, 4);
-            }
-            gl::Disable(gl::BLEND);
+        self.device.allocate_buffer(&self.solid_vertex_array.vertex_buffer,
+                                    &vertex_data,
+                                    BufferTarget::Vertex,
+                                    BufferUploadMode::Dynamic);
+
+        let uniforms = [
+            (&self.solid_program.framebuffer_size_uniform,
+             UniformData::Vec2(self.framebuffer_size.width as f32,
+                               self.framebuffer_size.height as f32)),
+            (&self.solid_program.color_uniform, color_to_uniform_data(color)),
+        ];
+        let render_state = RenderState {
+            vertex_array: &self.solid_vertex_array.vertex_array,
+            program: &self.solid_program.program,
+            uniforms: &uniforms,
+            textures: &[],
+            blend: Some(BlendState::ALPHA_OVER),
+        };
+
+        if filled {
+            self.device.draw_elements(Primitive::Triangles, 6, &render_state);
+        } else {
+            self.device.draw_arrays(Primitive::LineLoop, 4, &render_state);
         }
     }
 
@@ -205,12 +349,8 @@ impl DebugUI {
         let char_count = string.chars().count();
         let mut vertex_data = Vec::with_capacity(char_count * 4);
         let mut index_data = Vec::with_capacity(char_count * 6);
-        for mut character in string.chars() {
-            if !self.font.characters.contains_key(&character) {
-                character = '?';
-            }
-
-            let info = &self.font.characters[&character];
+        for character in string.chars() {
+            let info = self.glyph(character);
             let position_rect =
                 RectI32::new(Point2DI32::new(next.x() - info.origin_x, next.y() - info.origin_y),
                              Point2DI32::new(info.width as i32, info.height as i32));
@@ -233,8 +373,50 @@ impl DebugUI {
         self.draw_texture_with_vertex_data(&vertex_data, &index_data, &self.font_texture, color);
     }
 
-    pub fn draw_texture(&self, origin: Point2DI32, texture: &Texture, color: ColorU) {
-        let size = Point2DI32::new(texture.size.width as i32, texture.size.height as i32);
+    /// Draws `string` inside `rect`, wrapping at word boundaries and aligning each line.
+    pub fn draw_text_wrapped(&self, string: &str, rect: RectI32, align: TextAlign, invert: bool) {
+        let mut y = rect.min_y() + FONT_ASCENT;
+        for paragraph in string.split('\n') {
+            for line in self.wrap_paragraph(paragraph, rect.width()) {
+                let line_width = self.measure_text(&line);
+                let x = match align {
+                    TextAlign::Left => rect.min_x(),
+                    TextAlign::Center => rect.min_x() + (rect.width() - line_width) / 2,
+                    TextAlign::Right => rect.max_x() - line_width,
+                };
+                self.draw_text(&line, Point2DI32::new(x, y), invert);
+                y += LINE_HEIGHT;
+            }
+        }
+    }
+
+    // Greedily packs words from `paragraph` into lines no wider than `max_width`. Words are
+    // never split, so a single word wider than `max_width` gets a line of its own and overflows.
+    fn wrap_paragraph(&self, paragraph: &str, max_width: i32) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        let mut line_advance = 0;
+        for word in paragraph.split(' ') {
+            let word_advance = self.measure_text(word);
+            let space_advance = if line.is_empty() { 0 } else { self.measure_text(" ") };
+            if !line.is_empty() && line_advance + space_advance + word_advance > max_width {
+                lines.push(mem::replace(&mut line, String::new()));
+                line_advance = 0;
+            }
+            if !line.is_empty() {
+                line.push(' ');
+                line_advance += space_advance;
+            }
+            line.push_str(word);
+            line_advance += word_advance;
+        }
+        lines.push(line);
+        lines
+    }
+
+    pub fn draw_texture(&self, origin: Point2DI32, texture: &D::Texture, color: ColorU) {
+        let size = self.device.texture_size(texture);
+        let size = Point2DI32::new(size.width as i32, size.height as i32);
         let position_rect = RectI32::new(origin, size);
         let tex_coord_rect = RectI32::new(Point2DI32::default(), size);
         let vertex_data = [
@@ -247,157 +429,155 @@ impl DebugUI {
         self.draw_texture_with_vertex_data(&vertex_data, &QUAD_INDICES, texture, color);
     }
 
+    pub fn draw_button(&self, origin: Point2DI32, icon: &D::Texture, text: Option<&str>) -> bool {
+        let rect = RectI32::new(origin, Point2DI32::new(BUTTON_WIDTH, BUTTON_HEIGHT));
+        self.draw_solid_rect(rect, WINDOW_COLOR);
+        self.draw_texture(Point2DI32::new(origin.x() + PADDING, origin.y() + PADDING),
+                          icon,
+                          TEXT_COLOR);
+        if let Some(text) = text {
+            self.draw_text(text,
+                           Point2DI32::new(origin.x() + BUTTON_TEXT_OFFSET,
+                                           origin.y() + PADDING + FONT_ASCENT),
+                           false);
+        }
+        self.mouse_clicked.get() && rect.contains_point(self.mouse_position)
+    }
+
     pub fn measure_text(&self, string: &str) -> i32 {
         let mut next = 0;
-        for mut character in string.chars() {
-            if !self.font.characters.contains_key(&character) {
-                character = '?';
-            }
-
-            let info = &self.font.characters[&character];
-            next += info.advance;
+        for character in string.chars() {
+            next += self.glyph(character).advance;
         }
         next
     }
 
+    fn glyph(&self, character: char) -> &DebugCharacter {
+        let character = if self.font.characters.contains_key(&character) { character } else { '?' };
+        &self.font.characters[&character]
+    }
+
     fn draw_texture_with_vertex_data(&self,
                                      vertex_data: &[DebugTextureVertex],
                                      index_data: &[u32],
-                                     texture: &Texture,
+                                     texture: &D::Texture,
                                      color: ColorU) {
-        self.texture_vertex_array
-            .vertex_buffer
-            .upload(&vertex_data, BufferTarget::Vertex, BufferUploadMode::Dynamic);
-        self.texture_vertex_array
-            .index_buffer
-            .upload(&index_data, BufferTarget::Index, BufferUploadMode::Dynamic);
-
-        unsafe {
-            gl::BindVertexArray(self.texture_vertex_array.gl_vertex_array);
-            gl::UseProgram(self.texture_program.program.gl_program);
-            gl::Uniform2f(self.texture_program.framebuffer_size_uniform.location,
-                          self.framebuffer_size.width as GLfloat,
-                          self.framebuffer_size.height as GLfloat);
-            gl::Uniform2f(self.texture_program.texture_size_uniform.location,
-                          texture.size.width as GLfloat,
-                          texture.size.height as GLfloat);
-            set_color_uniform(&self.texture_program.color_uniform, color);
-            texture.bind(0);
-            gl::Uniform1i(self.texture_program.texture_uniform.location, 0);
-            gl::BlendEquation(gl::FUNC_ADD);
-            gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
-            gl::Enable(gl::BLEND);
-            gl::DrawElements(gl::TRIANGLES,
-                             index_data.len() as GLsizei,
-                             gl::UNSIGNED_INT,
-                             ptr::null());
-            gl::Disable(gl::BLEND);
-        }
+        self.device.allocate_buffer(&self.texture_vertex_array.vertex_buffer,
+                                    vertex_data,
+                                    BufferTarget::Vertex,
+                                    BufferUploadMode::Dynamic);
+        self.device.allocate_buffer(&self.texture_vertex_array.index_buffer,
+                                    index_data,
+                                    BufferTarget::Index,
+                                    BufferUploadMode::Dynamic);
+
+        let texture_size = self.device.texture_size(texture);
+        let uniforms = [
+            (&self.texture_program.framebuffer_size_uniform,
+             UniformData::Vec2(self.framebuffer_size.width as f32,
+                               self.framebuffer_size.height as f32)),
+            (&self.texture_program.texture_size_uniform,
+             UniformData::Vec2(texture_size.width as f32, texture_size.height as f32)),
+            (&self.texture_program.color_uniform, color_to_uniform_data(color)),
+        ];
+        let textures = [(&self.texture_program.texture_uniform, texture, 0)];
+        let render_state = RenderState {
+            vertex_array: &self.texture_vertex_array.vertex_array,
+            program: &self.texture_program.program,
+            uniforms: &uniforms,
+            textures: &textures,
+            blend: Some(BlendState::ALPHA_OVER),
+        };
+        self.device.draw_elements(Primitive::Triangles, index_data.len() as u32, &render_state);
     }
 }
 
-struct DebugTextureVertexArray {
-    gl_vertex_array: GLuint,
-    vertex_buffer: Buffer,
-    index_buffer: Buffer,
+struct DebugTextureVertexArray<D> where D: Device {
+    vertex_array: D::VertexArray,
+    vertex_buffer: D::Buffer,
+    index_buffer: D::Buffer,
 }
 
-impl DebugTextureVertexArray {
-    fn new(debug_texture_program: &DebugTextureProgram) -> DebugTextureVertexArray {
-        let vertex_buffer = Buffer::new();
-        let index_buffer = Buffer::new();
-        let mut gl_vertex_array = 0;
-        unsafe {
-            let position_attr = VertexAttr::new(&debug_texture_program.program, "Position");
-            let tex_coord_attr = VertexAttr::new(&debug_texture_program.program, "TexCoord");
-
-            gl::GenVertexArrays(1, &mut gl_vertex_array);
-            gl::BindVertexArray(gl_vertex_array);
-            gl::UseProgram(debug_texture_program.program.gl_program);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer.gl_buffer);
-            position_attr.configure_float(2,
-                                          gl::UNSIGNED_SHORT,
-                                          false,
-                                          DEBUG_TEXTURE_VERTEX_SIZE,
-                                          0,
-                                          0);
-            tex_coord_attr.configure_float(2,
-                                           gl::UNSIGNED_SHORT,
-                                           false,
-                                           DEBUG_TEXTURE_VERTEX_SIZE,
-                                           4,
-                                           0);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer.gl_buffer);
-        }
-
-        DebugTextureVertexArray { gl_vertex_array, vertex_buffer, index_buffer }
+impl<D> DebugTextureVertexArray<D> where D: Device {
+    fn new(device: &D, debug_texture_program: &DebugTextureProgram<D>) -> DebugTextureVertexArray<D> {
+        let vertex_buffer = device.create_buffer();
+        let index_buffer = device.create_buffer();
+        let vertex_array = device.create_vertex_array();
+
+        let position_attr = device.get_vertex_attr(&debug_texture_program.program, "Position");
+        let tex_coord_attr = device.get_vertex_attr(&debug_texture_program.program, "TexCoord");
+
+        device.bind_vertex_array(&vertex_array);
+        device.use_program(&debug_texture_program.program);
+        device.bind_buffer(&vertex_buffer, BufferTarget::Vertex);
+        device.configure_vertex_attr(&position_attr, &VertexAttrDescriptor {
+            size: 2,
+            attr_type: VertexAttrType::UnsignedShort,
+            normalized: false,
+            stride: DEBUG_TEXTURE_VERTEX_SIZE,
+            offset: 0,
+            divisor: 0,
+        });
+        device.configure_vertex_attr(&tex_coord_attr, &VertexAttrDescriptor {
+            size: 2,
+            attr_type: VertexAttrType::UnsignedShort,
+            normalized: false,
+            stride: DEBUG_TEXTURE_VERTEX_SIZE,
+            offset: 4,
+            divisor: 0,
+        });
+        device.bind_buffer(&index_buffer, BufferTarget::Index);
+
+        DebugTextureVertexArray { vertex_array, vertex_buffer, index_buffer }
     }
 }
 
-impl Drop for DebugTextureVertexArray {
-    #[inline]
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteVertexArrays(1, &mut self.gl_vertex_array);
-        }
-    }
+struct DebugSolidVertexArray<D> where D: Device {
+    vertex_array: D::VertexArray,
+    vertex_buffer: D::Buffer,
+    index_buffer: D::Buffer,
 }
 
-struct DebugSolidVertexArray {
-    gl_vertex_array: GLuint,
-    vertex_buffer: Buffer,
-    index_buffer: Buffer,
-}
-
-impl DebugSolidVertexArray {
-    fn new(debug_solid_program: &DebugSolidProgram) -> DebugSolidVertexArray {
-        let vertex_buffer = Buffer::new();
-        let index_buffer = Buffer::new();
-        let mut gl_vertex_array = 0;
-        unsafe {
-            let position_attr = VertexAttr::new(&debug_solid_program.program, "Position");
-
-            gl::GenVertexArrays(1, &mut gl_vertex_array);
-            gl::BindVertexArray(gl_vertex_array);
-            gl::UseProgram(debug_solid_program.program.gl_program);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vertex_buffer.gl_buffer);
-            position_attr.configure_float(2,
-                                          gl::UNSIGNED_SHORT,
-                                          false,
-                                          DEBUG_SOLID_VERTEX_SIZE,
-                                          0,
-                                          0);
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, index_buffer.gl_buffer);
-        }
-
-        DebugSolidVertexArray { gl_vertex_array, vertex_buffer, index_buffer }
+impl<D> DebugSolidVertexArray<D> where D: Device {
+    fn new(device: &D, debug_solid_program: &DebugSolidProgram<D>) -> DebugSolidVertexArray<D> {
+        let vertex_buffer = device.create_buffer();
+        let index_buffer = device.create_buffer();
+        let vertex_array = device.create_vertex_array();
+
+        let position_attr = device.get_vertex_attr(&debug_solid_program.program, "Position");
+
+        device.bind_vertex_array(&vertex_array);
+        device.use_program(&debug_solid_program.program);
+        device.bind_buffer(&vertex_buffer, BufferTarget::Vertex);
+        device.configure_vertex_attr(&position_attr, &VertexAttrDescriptor {
+            size: 2,
+            attr_type: VertexAttrType::UnsignedShort,
+            normalized: false,
+            stride: DEBUG_SOLID_VERTEX_SIZE,
+            offset: 0,
+            divisor: 0,
+        });
+        device.bind_buffer(&index_buffer, BufferTarget::Index);
+
+        DebugSolidVertexArray { vertex_array, vertex_buffer, index_buffer }
     }
 }
 
-impl Drop for DebugSolidVertexArray {
-    #[inline]
-    fn drop(&mut self) {
-        unsafe {
-            gl::DeleteVertexArrays(1, &mut self.gl_vertex_array);
-        }
-    }
+struct DebugTextureProgram<D> where D: Device {
+    program: D::Program,
+    framebuffer_size_uniform: D::Uniform,
+    texture_size_uniform: D::Uniform,
+    texture_uniform: D::Uniform,
+    color_uniform: D::Uniform,
 }
 
-struct DebugTextureProgram {
-    program: Program,
-    framebuffer_size_uniform: Uniform,
-    texture_size_uniform: Uniform,
-    texture_uniform: Uniform,
-    color_uniform: Uniform,
-}
-
-impl DebugTextureProgram {
-    fn new() -> DebugTextureProgram {
-        let program = Program::new("debug_texture");
-        let framebuffer_size_uniform = Uniform::new(&program, "FramebufferSize");
-        let texture_size_uniform = Uniform::new(&program, "TextureSize");
-        let texture_uniform = Uniform::new(&program, "Texture");
-        let color_uniform = Uniform::new(&program, "Color");
+impl<D> DebugTextureProgram<D> where D: Device {
+    fn new(device: &D) -> DebugTextureProgram<D> {
+        let program = device.create_program("debug_texture");
+        let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
+        let texture_size_uniform = device.get_uniform(&program, "TextureSize");
+        let texture_uniform = device.get_uniform(&program, "Texture");
+        let color_uniform = device.get_uniform(&program, "Color");
         DebugTextureProgram {
             program,
             framebuffer_size_uniform,
@@ -408,17 +588,17 @@ impl DebugTextureProgram {
     }
 }
 
-struct DebugSolidProgram {
-    program: Program,
-    framebuffer_size_uniform: Uniform,
-    color_uniform: Uniform,
+struct DebugSolidProgram<D> where D: Device {
+    program: D::Program,
+    framebuffer_size_uniform: D::Uniform,
+    color_uniform: D::Uniform,
 }
 
-impl DebugSolidProgram {
-    fn new() -> DebugSolidProgram {
-        let program = Program::new("debug_solid");
-        let framebuffer_size_uniform = Uniform::new(&program, "FramebufferSize");
-        let color_uniform = Uniform::new(&program, "Color");
+impl<D> DebugSolidProgram<D> where D: Device {
+    fn new(device: &D) -> DebugSolidProgram<D> {
+        let program = device.create_program("debug_solid");
+        let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
+        let color_uniform = device.get_uniform(&program, "Color");
         DebugSolidProgram { program, framebuffer_size_uniform, color_uniform }
     }
 }
@@ -456,7 +636,7 @@ impl DebugSolidVertex {
     }
 }
 
-struct SampleBuffer {
+pub struct SampleBuffer {
     samples: VecDeque<Duration>,
 }
 
@@ -479,18 +659,39 @@ impl SampleBuffer {
 
         let mut ms = 0.0;
         for time in &self.samples {
-            ms += time.as_secs() as f64 * 1000.0 + time.subsec_nanos() as f64 / 1000000.0;
+            ms += duration_to_ms(*time);
         }
         ms / self.samples.len() as f64
     }
+
+    pub fn min_ms(&self) -> f64 {
+        self.samples.iter().cloned().map(duration_to_ms).fold(None, fold_min).unwrap_or(0.0)
+    }
+
+    pub fn max_ms(&self) -> f64 {
+        self.samples.iter().cloned().map(duration_to_ms).fold(None, fold_max).unwrap_or(0.0)
+    }
+
+    pub fn iter(&self) -> vec_deque::Iter<Duration> {
+        self.samples.iter()
+    }
+}
+
+fn duration_to_ms(time: Duration) -> f64 {
+    time.as_secs() as f64 * 1000.0 + time.subsec_nanos() as f64 / 1000000.0
+}
+
+fn fold_min(acc: Option<f64>, value: f64) -> Option<f64> {
+    Some(acc.map_or(value, |acc| acc.min(value)))
+}
+
+fn fold_max(acc: Option<f64>, value: f64) -> Option<f64> {
+    Some(acc.map_or(value, |acc| acc.max(value)))
 }
 
-fn set_color_uniform(uniform: &Uniform, color: ColorU) {
-    unsafe {
-        gl::Uniform4f(uniform.location,
-                      color.r as f32 * (1.0 / 255.0),
+fn color_to_uniform_data(color: ColorU) -> UniformData {
+    UniformData::Vec4(color.r as f32 * (1.0 / 255.0),
                       color.g as f32 * (1.0 / 255.0),
                       color.b as f32 * (1.0 / 255.0),
-                      color.a as f32 * (1.0 / 255.0));
-    }
+                      color.a as f32 * (1.0 / 255.0))
 }
@@ -0,0 +1,422 @@
+// pathfinder/gl/src/device.rs
+//
+// Copyright © 2019 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A minimal graphics device abstraction, so the debug overlay isn't tied to raw GL calls.
+
+use euclid::Size2D;
+use gl::types::{GLchar, GLenum, GLint, GLsizeiptr, GLuint, GLvoid};
+use gl;
+use image;
+use std::ffi::CString;
+use std::mem;
+use std::ptr;
+
+pub trait Device {
+    type Buffer;
+    type Program;
+    type Texture;
+    type Uniform;
+    type VertexArray;
+    type VertexAttr;
+
+    fn create_buffer(&self) -> Self::Buffer;
+    fn allocate_buffer<T>(&self,
+                          buffer: &Self::Buffer,
+                          data: &[T],
+                          target: BufferTarget,
+                          mode: BufferUploadMode);
+
+    fn create_program(&self, name: &str) -> Self::Program;
+    fn use_program(&self, program: &Self::Program);
+    fn get_vertex_attr(&self, program: &Self::Program, name: &str) -> Self::VertexAttr;
+    fn get_uniform(&self, program: &Self::Program, name: &str) -> Self::Uniform;
+    fn set_uniform(&self, uniform: &Self::Uniform, data: UniformData);
+
+    fn create_vertex_array(&self) -> Self::VertexArray;
+    fn bind_vertex_array(&self, vertex_array: &Self::VertexArray);
+    fn bind_buffer(&self, buffer: &Self::Buffer, target: BufferTarget);
+    fn configure_vertex_attr(&self, attr: &Self::VertexAttr, descriptor: &VertexAttrDescriptor);
+
+    fn create_texture_from_png(&self, path: &str) -> Self::Texture;
+    fn texture_size(&self, texture: &Self::Texture) -> Size2D<u32>;
+    fn bind_texture(&self, texture: &Self::Texture, unit: u32);
+
+    fn draw_arrays(&self, primitive: Primitive, count: u32, render_state: &RenderState<Self>);
+    fn draw_elements(&self, primitive: Primitive, count: u32, render_state: &RenderState<Self>);
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BufferTarget {
+    Vertex,
+    Index,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BufferUploadMode {
+    Static,
+    Dynamic,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Primitive {
+    Triangles,
+    LineLoop,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VertexAttrType {
+    UnsignedShort,
+    Float,
+}
+
+/// Describes how a vertex attribute's data is laid out inside a vertex buffer.
+pub struct VertexAttrDescriptor {
+    pub size: i32,
+    pub attr_type: VertexAttrType,
+    pub normalized: bool,
+    pub stride: i32,
+    pub offset: usize,
+    pub divisor: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UniformData {
+    Int(i32),
+    Vec2(f32, f32),
+    Vec4(f32, f32, f32, f32),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendEquation {
+    Add,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlendFunc {
+    One,
+    OneMinusSrcAlpha,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlendState {
+    pub equation: BlendEquation,
+    pub src_func: BlendFunc,
+    pub dst_func: BlendFunc,
+}
+
+impl BlendState {
+    /// The `ONE, ONE_MINUS_SRC_ALPHA` blend used throughout the debug overlay.
+    pub const ALPHA_OVER: BlendState = BlendState {
+        equation: BlendEquation::Add,
+        src_func: BlendFunc::One,
+        dst_func: BlendFunc::OneMinusSrcAlpha,
+    };
+}
+
+/// Everything a single draw call needs: what to draw it with, and how to blend it.
+pub struct RenderState<'a, D> where D: Device + ?Sized {
+    pub vertex_array: &'a D::VertexArray,
+    pub program: &'a D::Program,
+    pub uniforms: &'a [(&'a D::Uniform, UniformData)],
+    pub textures: &'a [(&'a D::Uniform, &'a D::Texture, u32)],
+    pub blend: Option<BlendState>,
+}
+
+// === OpenGL backend ===
+
+pub struct GLDevice;
+
+impl GLDevice {
+    #[inline]
+    pub fn new() -> GLDevice {
+        GLDevice
+    }
+}
+
+pub struct GLBuffer {
+    pub gl_buffer: GLuint,
+}
+
+pub struct GLVertexArray {
+    pub gl_vertex_array: GLuint,
+}
+
+impl Drop for GLVertexArray {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &mut self.gl_vertex_array);
+        }
+    }
+}
+
+pub struct GLProgram {
+    pub gl_program: GLuint,
+    #[allow(dead_code)]
+    name: String,
+}
+
+pub struct GLUniform {
+    location: GLint,
+}
+
+pub struct GLVertexAttr {
+    attr: GLuint,
+}
+
+pub struct GLTexture {
+    pub gl_texture: GLuint,
+    pub size: Size2D<u32>,
+}
+
+impl Device for GLDevice {
+    type Buffer = GLBuffer;
+    type Program = GLProgram;
+    type Texture = GLTexture;
+    type Uniform = GLUniform;
+    type VertexArray = GLVertexArray;
+    type VertexAttr = GLVertexAttr;
+
+    fn create_buffer(&self) -> GLBuffer {
+        unsafe {
+            let mut gl_buffer = 0;
+            gl::GenBuffers(1, &mut gl_buffer);
+            GLBuffer { gl_buffer }
+        }
+    }
+
+    fn allocate_buffer<T>(&self,
+                          buffer: &GLBuffer,
+                          data: &[T],
+                          target: BufferTarget,
+                          mode: BufferUploadMode) {
+        let target = match target {
+            BufferTarget::Vertex => gl::ARRAY_BUFFER,
+            BufferTarget::Index => gl::ELEMENT_ARRAY_BUFFER,
+        };
+        let usage = match mode {
+            BufferUploadMode::Static => gl::STATIC_DRAW,
+            BufferUploadMode::Dynamic => gl::DYNAMIC_DRAW,
+        };
+        unsafe {
+            gl::BindBuffer(target, buffer.gl_buffer);
+            gl::BufferData(target,
+                           (data.len() * mem::size_of::<T>()) as GLsizeiptr,
+                           data.as_ptr() as *const GLvoid,
+                           usage);
+        }
+    }
+
+    fn create_program(&self, name: &str) -> GLProgram {
+        let vertex_source = resource_source(&format!("{}.vs.glsl", name));
+        let fragment_source = resource_source(&format!("{}.fs.glsl", name));
+        unsafe {
+            let vertex_shader = compile_shader(&vertex_source, gl::VERTEX_SHADER);
+            let fragment_shader = compile_shader(&fragment_source, gl::FRAGMENT_SHADER);
+            let gl_program = gl::CreateProgram();
+            gl::AttachShader(gl_program, vertex_shader);
+            gl::AttachShader(gl_program, fragment_shader);
+            gl::LinkProgram(gl_program);
+            GLProgram { gl_program, name: name.to_owned() }
+        }
+    }
+
+    fn use_program(&self, program: &GLProgram) {
+        unsafe {
+            gl::UseProgram(program.gl_program);
+        }
+    }
+
+    fn get_vertex_attr(&self, program: &GLProgram, name: &str) -> GLVertexAttr {
+        let name = CString::new(format!("a{}", name)).unwrap();
+        unsafe {
+            let attr = gl::GetAttribLocation(program.gl_program, name.as_ptr() as *const GLchar);
+            assert!(attr >= 0, "Vertex attribute '{}' not found!", name.to_str().unwrap());
+            GLVertexAttr { attr: attr as GLuint }
+        }
+    }
+
+    fn get_uniform(&self, program: &GLProgram, name: &str) -> GLUniform {
+        let name = CString::new(format!("u{}", name)).unwrap();
+        unsafe {
+            let location = gl::GetUniformLocation(program.gl_program,
+                                                  name.as_ptr() as *const GLchar);
+            GLUniform { location }
+        }
+    }
+
+    fn set_uniform(&self, uniform: &GLUniform, data: UniformData) {
+        unsafe {
+            match data {
+                UniformData::Int(value) => gl::Uniform1i(uniform.location, value),
+                UniformData::Vec2(x, y) => gl::Uniform2f(uniform.location, x, y),
+                UniformData::Vec4(x, y, z, w) => gl::Uniform4f(uniform.location, x, y, z, w),
+            }
+        }
+    }
+
+    fn create_vertex_array(&self) -> GLVertexArray {
+        unsafe {
+            let mut gl_vertex_array = 0;
+            gl::GenVertexArrays(1, &mut gl_vertex_array);
+            GLVertexArray { gl_vertex_array }
+        }
+    }
+
+    fn bind_vertex_array(&self, vertex_array: &GLVertexArray) {
+        unsafe {
+            gl::BindVertexArray(vertex_array.gl_vertex_array);
+        }
+    }
+
+    fn bind_buffer(&self, buffer: &GLBuffer, target: BufferTarget) {
+        let target = match target {
+            BufferTarget::Vertex => gl::ARRAY_BUFFER,
+            BufferTarget::Index => gl::ELEMENT_ARRAY_BUFFER,
+        };
+        unsafe {
+            gl::BindBuffer(target, buffer.gl_buffer);
+        }
+    }
+
+    fn configure_vertex_attr(&self, attr: &GLVertexAttr, descriptor: &VertexAttrDescriptor) {
+        let attr_type = match descriptor.attr_type {
+            VertexAttrType::UnsignedShort => gl::UNSIGNED_SHORT,
+            VertexAttrType::Float => gl::FLOAT,
+        };
+        unsafe {
+            gl::VertexAttribPointer(attr.attr,
+                                    descriptor.size,
+                                    attr_type,
+                                    descriptor.normalized as u8,
+                                    descriptor.stride,
+                                    descriptor.offset as *const GLvoid);
+            if descriptor.divisor > 0 {
+                gl::VertexAttribDivisor(attr.attr, descriptor.divisor);
+            }
+            gl::EnableVertexAttribArray(attr.attr);
+        }
+    }
+
+    fn create_texture_from_png(&self, path: &str) -> GLTexture {
+        let image = image::open(path).unwrap().to_rgba();
+        let size = Size2D::new(image.width(), image.height());
+        unsafe {
+            let mut gl_texture = 0;
+            gl::GenTextures(1, &mut gl_texture);
+            gl::BindTexture(gl::TEXTURE_2D, gl_texture);
+            gl::TexImage2D(gl::TEXTURE_2D,
+                           0,
+                           gl::RGBA as GLint,
+                           size.width as GLint,
+                           size.height as GLint,
+                           0,
+                           gl::RGBA,
+                           gl::UNSIGNED_BYTE,
+                           image.into_raw().as_ptr() as *const GLvoid);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            GLTexture { gl_texture, size }
+        }
+    }
+
+    fn texture_size(&self, texture: &GLTexture) -> Size2D<u32> {
+        texture.size
+    }
+
+    fn bind_texture(&self, texture: &GLTexture, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, texture.gl_texture);
+        }
+    }
+
+    fn draw_arrays(&self, primitive: Primitive, count: u32, render_state: &RenderState<GLDevice>) {
+        self.set_render_state(render_state);
+        unsafe {
+            gl::DrawArrays(primitive_to_gl(primitive), 0, count as GLint);
+        }
+        self.unset_render_state(render_state);
+    }
+
+    fn draw_elements(&self, primitive: Primitive, count: u32, render_state: &RenderState<GLDevice>) {
+        self.set_render_state(render_state);
+        unsafe {
+            gl::DrawElements(primitive_to_gl(primitive), count as GLint, gl::UNSIGNED_INT, ptr::null());
+        }
+        self.unset_render_state(render_state);
+    }
+}
+
+impl GLDevice {
+    fn set_render_state(&self, render_state: &RenderState<GLDevice>) {
+        self.bind_vertex_array(render_state.vertex_array);
+        self.use_program(render_state.program);
+        for &(uniform, data) in render_state.uniforms {
+            self.set_uniform(uniform, data);
+        }
+        for &(uniform, texture, unit) in render_state.textures {
+            self.bind_texture(texture, unit);
+            self.set_uniform(uniform, UniformData::Int(unit as i32));
+        }
+        if let Some(blend) = render_state.blend {
+            unsafe {
+                gl::BlendEquation(blend_equation_to_gl(blend.equation));
+                gl::BlendFunc(blend_func_to_gl(blend.src_func), blend_func_to_gl(blend.dst_func));
+                gl::Enable(gl::BLEND);
+            }
+        }
+    }
+
+    fn unset_render_state(&self, render_state: &RenderState<GLDevice>) {
+        if render_state.blend.is_some() {
+            unsafe {
+                gl::Disable(gl::BLEND);
+            }
+        }
+    }
+}
+
+fn primitive_to_gl(primitive: Primitive) -> GLenum {
+    match primitive {
+        Primitive::Triangles => gl::TRIANGLES,
+        Primitive::LineLoop => gl::LINE_LOOP,
+    }
+}
+
+fn blend_equation_to_gl(equation: BlendEquation) -> GLenum {
+    match equation {
+        BlendEquation::Add => gl::FUNC_ADD,
+    }
+}
+
+fn blend_func_to_gl(func: BlendFunc) -> GLenum {
+    match func {
+        BlendFunc::One => gl::ONE,
+        BlendFunc::OneMinusSrcAlpha => gl::ONE_MINUS_SRC_ALPHA,
+    }
+}
+
+fn resource_source(name: &str) -> String {
+    use std::fs::File;
+    use std::io::Read;
+    let path = format!("resources/shaders/{}", name);
+    let mut source = String::new();
+    File::open(&path).unwrap().read_to_string(&mut source).unwrap();
+    source
+}
+
+unsafe fn compile_shader(source: &str, kind: GLenum) -> GLuint {
+    let shader = gl::CreateShader(kind);
+    let source = CString::new(source.as_bytes()).unwrap();
+    gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+    gl::CompileShader(shader);
+    shader
+}